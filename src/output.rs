@@ -1,8 +1,18 @@
-use cansi::{CategorisedSlice, Color};
 use eframe::egui::{vec2, Color32, Label, ProgressBar, Ui};
 use linkify::{LinkFinder, LinkKind};
+use serde_json::{Map, Value};
+use std::cell::Cell;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use vte::{Params, Parser, Perform};
 
 /// Displays a progress bar in the output. First call creates
 /// a progress bar and future calls update it.
@@ -46,41 +56,599 @@ pub fn progress_bar(description: &str, value: f32) {
 /// }
 /// ```
 pub fn progress_bar_with_id(id: impl Hash, description: &str, value: f32) {
+    send_progress_bar(
+        id,
+        ProgressBarState {
+            description: description.to_string(),
+            value: ProgressValue::Fraction(value),
+            template: None,
+            group: current_group(),
+        },
+    )
+}
+
+/// Displays a progress bar tracking an integer `position` out of `length`
+/// (e.g. items processed out of a total count), rather than a pre-computed
+/// fraction. The GUI derives throughput and an ETA from how `position`
+/// changes over time, the way `indicatif` does.
+///
+/// The default template (`{bar} {pos}/{len} ({eta}, {rate}/s)`) has no
+/// `{msg}`, so `description` is not displayed; it only serves as the id.
+/// [`progress_bar_with_template`] with a `{msg}` placeholder displays it.
+///
+/// If the description is not static, use [`progress_count_with_id`].
+pub fn progress_count(description: &str, position: u64, length: u64) {
+    progress_count_with_id(description, description, position, length)
+}
+
+/// Same as [`progress_count`], but with an explicit id for a non-static description.
+pub fn progress_count_with_id(id: impl Hash, description: &str, position: u64, length: u64) {
+    send_progress_bar(
+        id,
+        ProgressBarState {
+            description: description.to_string(),
+            value: ProgressValue::Count {
+                position,
+                length,
+                bytes: false,
+            },
+            template: None,
+            group: current_group(),
+        },
+    )
+}
+
+/// Same as [`progress_count`], but `position`/`length` are byte counts and are
+/// rendered with human-readable units (e.g. `1.5MiB/4.0MiB`).
+pub fn progress_bytes(description: &str, position: u64, length: u64) {
+    progress_bytes_with_id(description, description, position, length)
+}
+
+/// Same as [`progress_bytes`], but with an explicit id for a non-static description.
+pub fn progress_bytes_with_id(id: impl Hash, description: &str, position: u64, length: u64) {
+    send_progress_bar(
+        id,
+        ProgressBarState {
+            description: description.to_string(),
+            value: ProgressValue::Count {
+                position,
+                length,
+                bytes: true,
+            },
+            template: None,
+            group: current_group(),
+        },
+    )
+}
+
+/// Same as [`progress_count_with_id`]/[`progress_bytes_with_id`], but lets the
+/// caller pick which fields appear and in what order, mirroring `indicatif`'s
+/// template strings. Recognised placeholders: `{msg}`, `{pos}`, `{len}`,
+/// `{eta}`, `{rate}`/`{bytes_per_sec}`. `{bar}`/`{wide_bar}` are accepted for
+/// familiarity but are no-ops: the progress bar itself is always drawn as the
+/// widget background, the template only controls the text drawn over it.
+pub fn progress_bar_with_template(
+    id: impl Hash,
+    description: &str,
+    position: u64,
+    length: u64,
+    bytes: bool,
+    template: &str,
+) {
+    send_progress_bar(
+        id,
+        ProgressBarState {
+            description: description.to_string(),
+            value: ProgressValue::Count {
+                position,
+                length,
+                bytes,
+            },
+            template: Some(template.to_string()),
+            group: current_group(),
+        },
+    )
+}
+
+fn send_progress_bar(id: impl Hash, state: ProgressBarState) {
     let mut h = DefaultHasher::new();
     id.hash(&mut h);
-    OutputType::ProgressBar(description.to_string(), value).send(h.finish());
+    OutputType::ProgressBar(state).send(h.finish());
+}
+
+/// Displays an indeterminate spinner next to `message`, for when the total
+/// amount of work is unknown. Unlike [`progress_bar`], the spinner keeps
+/// animating on its own steady tick in the GUI even if the program doesn't
+/// call this again for a while; call [`spinner_finish`] once the work is done.
+/// Id is any hashable value that uniquely identifies a spinner.
+/// ```no_run
+/// # use clap::{App, Arg};
+/// # use klask::Settings;
+/// fn main() {
+///     klask::run_app(App::new("Example"), Settings::default(), |matches| {
+///         klask::output::spinner("download", "Downloading...");
+///         // ... do the work ...
+///         klask::output::spinner_finish("download", "Done!");
+///     });
+/// }
+/// ```
+pub fn spinner(id: impl Hash, message: &str) {
+    send_spinner(id, message, false)
+}
+
+/// Marks the spinner identified by `id` as finished: the animation freezes
+/// and `message` is shown as its final state.
+pub fn spinner_finish(id: impl Hash, message: &str) {
+    send_spinner(id, message, true)
+}
+
+fn send_spinner(id: impl Hash, message: &str, finished: bool) {
+    let mut h = DefaultHasher::new();
+    id.hash(&mut h);
+    OutputType::Spinner(SpinnerState {
+        message: message.to_string(),
+        finished,
+        group: current_group(),
+    })
+    .send(h.finish());
+}
+
+/// Counter handing out a fresh id to each [`highlight_block`] call. Unlike
+/// progress bars/spinners, a code block is never updated in place, so it
+/// doesn't need a caller-supplied id to key future updates by - it just
+/// needs one that won't collide with a previous block's.
+static NEXT_HIGHLIGHT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Renders `code` as a syntax-highlighted block, the way [`format_output`]
+/// renders ANSI spans, using `syntect` with `language` as the syntax token
+/// (e.g. `"rust"`, `"diff"`, `"toml"`; falls back to plain text if unknown).
+/// ```no_run
+/// # use clap::{App, Arg};
+/// # use klask::Settings;
+/// fn main() {
+///     klask::run_app(App::new("Example"), Settings::default(), |matches| {
+///         klask::output::highlight_block("rust", "fn main() {}");
+///     });
+/// }
+/// ```
+pub fn highlight_block(language: &str, code: &str) {
+    let id = NEXT_HIGHLIGHT_ID.fetch_add(1, Ordering::Relaxed);
+    OutputType::Highlight {
+        language: language.to_string(),
+        code: code.to_string(),
+    }
+    .send(id);
+}
+
+/// Safety window after which a [`begin_synchronized_update`] batch that was
+/// never closed with [`end_synchronized_update`] (e.g. the wrapped program
+/// crashed mid-update) is force-committed, so the display can't freeze.
+/// Mirrors how terminal emulators bound their own synchronized-update mode.
+const SYNC_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Marks the start of a batch of output updates that should appear on
+/// screen atomically instead of as each one arrives, eliminating flicker
+/// when a large region (a table, a dashboard) is redrawn. Borrowed from
+/// terminal emulators' own "synchronized update" escape sequences. Pair
+/// with [`end_synchronized_update`]; an unpaired `begin` is force-committed
+/// after roughly [`SYNC_TIMEOUT`] so a crash mid-batch can't freeze the display.
+///
+/// Unlike [`progress_group`], batches don't nest: calling this again before
+/// [`end_synchronized_update`] commits whatever was buffered so far rather
+/// than merging with or discarding it.
+/// ```no_run
+/// # use clap::{App, Arg};
+/// # use klask::Settings;
+/// fn main() {
+///     klask::run_app(App::new("Example"), Settings::default(), |matches| {
+///         klask::output::begin_synchronized_update();
+///         for row in 0..10 {
+///             klask::output::progress_bar_with_id(row, &format!("Row {row}"), 0.5);
+///         }
+///         klask::output::end_synchronized_update();
+///     });
+/// }
+/// ```
+pub fn begin_synchronized_update() {
+    OutputType::SyncBegin.send(0);
+}
+
+/// Ends a batch started by [`begin_synchronized_update`], swapping its
+/// buffered updates into the display in a single step.
+pub fn end_synchronized_update() {
+    OutputType::SyncEnd.send(0);
+}
+
+thread_local! {
+    /// The group that newly-sent progress bars/spinners join, set by [`progress_group`].
+    static CURRENT_GROUP: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+fn current_group() -> Option<u64> {
+    CURRENT_GROUP.with(Cell::get)
+}
+
+/// Groups every progress bar/spinner sent while the returned guard is alive
+/// into a single contiguous block in the GUI, keyed by `group_id`, the way
+/// `indicatif::MultiProgress` stacks related bars together instead of
+/// scattering them through the log. Groups can be nested.
+///
+/// To also show a title line above the group's bars, use [`progress_group_with_header`].
+/// ```no_run
+/// # use clap::{App, Arg};
+/// # use klask::Settings;
+/// fn main() {
+///     klask::run_app(App::new("Example"), Settings::default(), |matches| {
+///         let _group = klask::output::progress_group("downloads");
+///         klask::output::progress_bytes_with_id("file1", "file1.zip", 0, 100);
+///         klask::output::progress_bytes_with_id("file2", "file2.zip", 0, 200);
+///     });
+/// }
+/// ```
+#[must_use = "the group ends when this guard is dropped"]
+pub fn progress_group(group_id: impl Hash) -> ProgressGroup {
+    progress_group_with_header(group_id, "")
+}
+
+/// Same as [`progress_group`], but also renders `header` as a title line above the group's bars.
+#[must_use = "the group ends when this guard is dropped"]
+pub fn progress_group_with_header(group_id: impl Hash, header: &str) -> ProgressGroup {
+    let mut h = DefaultHasher::new();
+    group_id.hash(&mut h);
+    let id = h.finish();
+
+    if !header.is_empty() {
+        OutputType::GroupHeader(header.to_string()).send(id);
+    }
+
+    let previous = CURRENT_GROUP.with(|current| current.replace(Some(id)));
+    ProgressGroup { previous }
+}
+
+/// RAII guard returned by [`progress_group`]; restores the enclosing group
+/// (if any) when dropped.
+pub struct ProgressGroup {
+    previous: Option<u64>,
+}
+
+impl Drop for ProgressGroup {
+    fn drop(&mut self) {
+        CURRENT_GROUP.with(|current| current.set(self.previous));
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct Output(Vec<(u64, OutputType)>);
+pub(crate) struct Output {
+    entries: Vec<(u64, OutputType)>,
+    progress_timings: HashMap<u64, ProgressTiming>,
+    spinner_timings: HashMap<u64, SpinnerTiming>,
+    json_log_template: Option<JsonLogTemplate>,
+    sync_buffer: Option<SyncBuffer>,
+}
 
 #[derive(Debug)]
 pub(crate) enum OutputType {
     Text(String),
-    ProgressBar(String, f32),
+    ProgressBar(ProgressBarState),
+    Spinner(SpinnerState),
+    /// A title line rendered above a progress group's bars, keyed under that
+    /// group's own id. See [`progress_group_with_header`].
+    GroupHeader(String),
+    /// A block of source code to render with syntax highlighting. See
+    /// [`highlight_block`].
+    Highlight {
+        language: String,
+        code: String,
+    },
+    /// A single structured (JSON-object) log line, pre-parsed according to
+    /// a [`JsonLogTemplate`]. See [`Output::set_json_log_template`].
+    JsonLog(JsonLogEntry),
+    /// Starts buffering updates for an atomic swap. See
+    /// [`begin_synchronized_update`].
+    SyncBegin,
+    /// Ends a batch started by `SyncBegin`. See [`end_synchronized_update`].
+    SyncEnd,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ProgressBarState {
+    description: String,
+    value: ProgressValue,
+    template: Option<String>,
+    group: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SpinnerState {
+    message: String,
+    finished: bool,
+    group: Option<u64>,
+}
+
+impl OutputType {
+    /// The progress group this entry belongs to, if any. Entries sharing a
+    /// group are rendered as one contiguous block by [`Output::update`].
+    fn group(&self) -> Option<u64> {
+        match self {
+            OutputType::ProgressBar(ProgressBarState { group, .. }) => *group,
+            OutputType::Spinner(SpinnerState { group, .. }) => *group,
+            OutputType::Text(_)
+            | OutputType::GroupHeader(_)
+            | OutputType::Highlight { .. }
+            | OutputType::JsonLog(_)
+            | OutputType::SyncBegin
+            | OutputType::SyncEnd => None,
+        }
+    }
+}
+
+/// Configures how [`Output::set_json_log_template`] recognises and renders
+/// JSON-object log lines (one `tracing`/`slog`-style structured log entry
+/// per line) instead of showing them as a wall of raw braces.
+///
+/// `message_field` is shown first, highlighted, and colored according to
+/// `level_field` (`"error"`/`"warn"`/`"info"` colored, `"debug"`/`"trace"`
+/// faint, anything else/missing just highlighted). Fields named via
+/// [`with_main_field`](Self::with_main_field) are shown inline after it;
+/// every other top-level field is shown as a dimmed `key=value` pair,
+/// except `level_field`, which is not shown as text unless it is also
+/// added with `with_main_field`.
+/// Lines that aren't a JSON object, or don't have `message_field`, fall
+/// back to pretty-printed JSON (if valid JSON at all) or plain text.
+#[derive(Debug, Clone)]
+pub struct JsonLogTemplate {
+    message_field: String,
+    level_field: String,
+    main_fields: Vec<String>,
+}
+
+impl JsonLogTemplate {
+    pub fn new(message_field: &str, level_field: &str) -> Self {
+        Self {
+            message_field: message_field.to_string(),
+            level_field: level_field.to_string(),
+            main_fields: Vec::new(),
+        }
+    }
+
+    /// Also show `field` inline on the main line (in the order added),
+    /// instead of folding it into the dimmed trailing `key=value` list.
+    pub fn with_main_field(mut self, field: &str) -> Self {
+        self.main_fields.push(field.to_string());
+        self
+    }
+
+    fn is_main(&self, key: &str) -> bool {
+        key == self.message_field
+            || key == self.level_field
+            || self.main_fields.iter().any(|f| f == key)
+    }
+
+    fn parse_line(&self, object: &Map<String, Value>) -> Option<JsonLogEntry> {
+        let message = json_scalar_to_string(object.get(&self.message_field)?);
+        let style = object
+            .get(&self.level_field)
+            .and_then(Value::as_str)
+            .map_or_else(
+                || AnsiStyle {
+                    bold: true,
+                    ..AnsiStyle::default()
+                },
+                level_style,
+            );
+
+        let main = self
+            .main_fields
+            .iter()
+            .filter_map(|field| {
+                object
+                    .get(field)
+                    .map(|value| (field.clone(), json_scalar_to_string(value)))
+            })
+            .collect();
+
+        let mut extra: Vec<_> = object
+            .iter()
+            .filter(|(key, _)| !self.is_main(key))
+            .map(|(key, value)| (key.clone(), json_scalar_to_string(value)))
+            .collect();
+        extra.sort();
+
+        Some(JsonLogEntry {
+            style,
+            message,
+            main,
+            extra,
+        })
+    }
+}
+
+/// A pre-parsed structured log line, rendered with the same [`AnsiStyle`]/
+/// [`style_label`] pipeline as ANSI and syntax-highlighted output.
+#[derive(Debug, Clone)]
+pub(crate) struct JsonLogEntry {
+    style: AnsiStyle,
+    message: String,
+    main: Vec<(String, String)>,
+    extra: Vec<(String, String)>,
+}
+
+/// Maps a level field's value to the style its message line is rendered in.
+fn level_style(level: &str) -> AnsiStyle {
+    match level.to_ascii_lowercase().as_str() {
+        "error" => AnsiStyle {
+            fg: Some(NAMED_COLORS[9]),
+            bold: true,
+            ..AnsiStyle::default()
+        },
+        "warn" | "warning" => AnsiStyle {
+            fg: Some(NAMED_COLORS[11]),
+            bold: true,
+            ..AnsiStyle::default()
+        },
+        "info" => AnsiStyle {
+            fg: Some(NAMED_COLORS[10]),
+            bold: true,
+            ..AnsiStyle::default()
+        },
+        "debug" | "trace" => AnsiStyle {
+            faint: true,
+            ..AnsiStyle::default()
+        },
+        _ => AnsiStyle {
+            bold: true,
+            ..AnsiStyle::default()
+        },
+    }
+}
+
+/// Renders a JSON value the way it should appear on one side of a
+/// `key=value` pair: strings unquoted, everything else via its JSON form.
+fn json_scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ProgressValue {
+    Fraction(f32),
+    Count {
+        position: u64,
+        length: u64,
+        bytes: bool,
+    },
+}
+
+/// Default template for integer-based progress bars, mirroring `indicatif`'s
+/// own default: `{bar} {pos}/{len} ({eta}, {rate}/s)`.
+const DEFAULT_COUNT_TEMPLATE: &str = "{bar} {pos}/{len} ({eta}, {rate}/s)";
+
+/// How many recent `(time, position)` samples to keep per progress bar when
+/// smoothing throughput, similar to `indicatif`'s steady-tick sampling window.
+const THROUGHPUT_WINDOW: usize = 20;
+
+/// Per-id timing state used to compute a smoothed throughput and ETA for
+/// integer-based progress bars, kept across frames on [`Output`].
+#[derive(Debug)]
+struct ProgressTiming {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl ProgressTiming {
+    fn new(position: u64) -> Self {
+        let mut samples = VecDeque::with_capacity(THROUGHPUT_WINDOW);
+        samples.push_back((Instant::now(), position));
+        Self { samples }
+    }
+
+    fn record(&mut self, position: u64) {
+        self.samples.push_back((Instant::now(), position));
+        if self.samples.len() > THROUGHPUT_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Smoothed throughput in units/sec, estimated as the slope between the
+    /// oldest and newest sample still in the window.
+    fn rate(&self) -> f64 {
+        let (Some(&(t0, p0)), Some(&(t1, p1))) = (self.samples.front(), self.samples.back()) else {
+            return 0.0;
+        };
+        let elapsed = t1.duration_since(t0).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        p1.saturating_sub(p0) as f64 / elapsed
+    }
+
+    /// ETA via linear extrapolation of the remaining work over the observed rate.
+    fn eta(&self, position: u64, length: u64) -> Option<Duration> {
+        let rate = self.rate();
+        if rate <= 0.0 || position >= length {
+            return None;
+        }
+        Some(Duration::from_secs_f64((length - position) as f64 / rate))
+    }
+}
+
+/// Animation frames for an in-progress [`OutputType::Spinner`], advanced on
+/// [`SPINNER_TICK`] regardless of how often the user program sends updates.
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_TICK: Duration = Duration::from_millis(80);
+
+/// Per-id animation state for an in-progress spinner, kept across frames on
+/// [`Output`] so it keeps advancing even while the worker it represents is blocked.
+#[derive(Debug)]
+struct SpinnerTiming {
+    start: Instant,
+}
+
+impl SpinnerTiming {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    fn frame(&self) -> char {
+        let ticks = self.start.elapsed().as_millis() / SPINNER_TICK.as_millis();
+        SPINNER_FRAMES[ticks as usize % SPINNER_FRAMES.len()]
+    }
+}
+
+/// Entries staged between a [`OutputType::SyncBegin`]/[`OutputType::SyncEnd`]
+/// pair, swapped into [`Output::entries`](Output) as one step on commit.
+#[derive(Debug)]
+struct SyncBuffer {
+    entries: Vec<(u64, OutputType)>,
+    started_at: Instant,
 }
 
 impl Output {
     pub fn new() -> Self {
-        Self(vec![])
+        Self {
+            entries: vec![],
+            progress_timings: HashMap::new(),
+            spinner_timings: HashMap::new(),
+            json_log_template: None,
+            sync_buffer: None,
+        }
+    }
+
+    /// Opts into rendering JSON-object lines as colored structured log
+    /// entries instead of raw text. See [`JsonLogTemplate`].
+    pub fn set_json_log_template(&mut self, template: JsonLogTemplate) {
+        self.json_log_template = Some(template);
     }
 
     pub fn parse(&mut self, str: &str) {
+        // Cloned up front so it can be read while `entries_mut` below holds
+        // a mutable borrow of the whole of `self`.
+        let json_log_template = self.json_log_template.clone();
         let mut iter = str.split(MAGIC);
 
         if let Some(text) = iter.next() {
-            if !text.is_empty() {
-                self.0.push((0, OutputType::Text(text.to_string())))
-            }
+            push_text(self.entries_mut(), json_log_template.as_ref(), text);
         }
 
         while let Some(id) = iter.next() {
             if let Ok(id) = id.parse() {
                 if let Some(new) = OutputType::parse(&mut iter) {
-                    if let Some((_, exists)) = self.0.iter_mut().find(|(i, _)| *i == id) {
-                        *exists = new;
-                    } else {
-                        self.0.push((id, new));
+                    match new {
+                        OutputType::SyncBegin => {
+                            // Batches don't nest (unlike `progress_group`): a
+                            // `begin` received while one is already open commits
+                            // whatever it buffered so far rather than discarding it.
+                            self.commit_sync_buffer();
+                            self.sync_buffer = Some(SyncBuffer {
+                                entries: Vec::new(),
+                                started_at: Instant::now(),
+                            });
+                        }
+                        OutputType::SyncEnd => self.commit_sync_buffer(),
+                        new => self.record_entry(id, new),
                     }
                 }
             }
@@ -88,22 +656,390 @@ impl Output {
             if let Some(text) = iter.next() {
                 // Get rid of the newline
                 let text = &text[1..];
-                if !text.is_empty() {
-                    self.0.push((0, OutputType::Text(text.to_string())))
+                push_text(self.entries_mut(), json_log_template.as_ref(), text);
+            }
+        }
+    }
+
+    /// The entry list updates are currently applied to: the buffered batch
+    /// while one is open (see [`OutputType::SyncBegin`]), otherwise the
+    /// displayed list directly.
+    fn entries_mut(&mut self) -> &mut Vec<(u64, OutputType)> {
+        match &mut self.sync_buffer {
+            Some(buffer) => &mut buffer.entries,
+            None => &mut self.entries,
+        }
+    }
+
+    /// Records a parsed, non-sync-marker message: updates its timing
+    /// bookkeeping and inserts/updates it in [`Self::entries_mut`].
+    fn record_entry(&mut self, id: u64, new: OutputType) {
+        if let OutputType::ProgressBar(ProgressBarState {
+            value: ProgressValue::Count { position, .. },
+            ..
+        }) = &new
+        {
+            self.progress_timings
+                .entry(id)
+                .and_modify(|timing| timing.record(*position))
+                .or_insert_with(|| ProgressTiming::new(*position));
+        }
+
+        if let OutputType::Spinner(SpinnerState { finished, .. }) = &new {
+            if *finished {
+                self.spinner_timings.remove(&id);
+            } else {
+                self.spinner_timings
+                    .entry(id)
+                    .or_insert_with(SpinnerTiming::new);
+            }
+        }
+
+        let entries = self.entries_mut();
+        if let Some((_, exists)) = entries.iter_mut().find(|(i, _)| *i == id) {
+            *exists = new;
+        } else {
+            entries.push((id, new));
+        }
+    }
+
+    /// Swaps a finished (or timed-out) synchronized batch into the
+    /// displayed entries in one step, using the same insert-or-update
+    /// semantics as an unbuffered message. Only entry kinds that are
+    /// genuinely meant to update in place are looked up by id; `Text`/
+    /// `JsonLog` entries all share the `id == 0` sentinel (see
+    /// [`push_text`]) and are always appended, matching its own semantics.
+    fn commit_sync_buffer(&mut self) {
+        let Some(buffer) = self.sync_buffer.take() else {
+            return;
+        };
+
+        for (id, new) in buffer.entries {
+            let keyed = matches!(
+                new,
+                OutputType::ProgressBar(_) | OutputType::Spinner(_) | OutputType::GroupHeader(_)
+            );
+
+            if keyed {
+                if let Some((_, exists)) = self.entries.iter_mut().find(|(i, _)| *i == id) {
+                    *exists = new;
+                    continue;
                 }
             }
+
+            self.entries.push((id, new));
         }
     }
 
     pub fn update(&mut self, ui: &mut Ui) {
-        for (_, o) in &mut self.0 {
+        // A never-closed batch can't freeze the display forever: force a
+        // commit once the timeout has elapsed, and keep polling for it by
+        // requesting a repaint - egui's reactive mode won't call us again
+        // on its own just because time passed.
+        if let Some(buffer) = &self.sync_buffer {
+            if buffer.started_at.elapsed() >= SYNC_TIMEOUT {
+                self.commit_sync_buffer();
+            } else {
+                ui.ctx().request_repaint_after(SYNC_TIMEOUT);
+            }
+        }
+
+        // Split into disjoint borrows: rendering a group needs to look back
+        // across the whole entry list while the timing maps stay mutable.
+        let Output {
+            entries,
+            progress_timings,
+            spinner_timings,
+            json_log_template: _,
+            sync_buffer: _,
+        } = self;
+
+        let mut rendered_groups = HashSet::new();
+        for (id, o) in entries.iter() {
+            if let Some(group_id) = o.group() {
+                if !rendered_groups.insert(group_id) {
+                    continue; // Already drawn as part of that group's block.
+                }
+                render_group(ui, entries, progress_timings, spinner_timings, group_id);
+                continue;
+            }
+
+            render_entry(ui, *id, o, progress_timings, spinner_timings);
+        }
+    }
+}
+
+/// Appends a chunk of plain output text as entries. Without a
+/// [`JsonLogTemplate`], this is a no-op pass-through that keeps the whole
+/// chunk as one [`OutputType::Text`], exactly like before JSON-log mode
+/// existed. With one, the chunk is split line by line so each JSON-object
+/// line can be rendered as its own [`OutputType::JsonLog`]; a line that
+/// parses as JSON but doesn't fit the template is pretty-printed instead.
+fn push_text(
+    entries: &mut Vec<(u64, OutputType)>,
+    json_log_template: Option<&JsonLogTemplate>,
+    text: &str,
+) {
+    let Some(template) = json_log_template else {
+        if !text.is_empty() {
+            entries.push((0, OutputType::Text(text.to_string())));
+        }
+        return;
+    };
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches('\n');
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let Ok(value) = serde_json::from_str::<Value>(trimmed) else {
+            entries.push((0, OutputType::Text(line.to_string())));
+            continue;
+        };
+
+        match value
+            .as_object()
+            .and_then(|object| template.parse_line(object))
+        {
+            Some(entry) => entries.push((0, OutputType::JsonLog(entry))),
+            None => {
+                let pretty =
+                    serde_json::to_string_pretty(&value).unwrap_or_else(|_| line.to_string());
+                entries.push((0, OutputType::Text(pretty)));
+            }
+        }
+    }
+}
+
+/// Renders a single entry, either on its own or, via [`render_group`], as
+/// a member of a group's block. `GroupHeader` entries are only ever
+/// rendered by [`render_group`] itself, so they're a no-op here.
+fn render_entry(
+    ui: &mut Ui,
+    id: u64,
+    o: &OutputType,
+    progress_timings: &mut HashMap<u64, ProgressTiming>,
+    spinner_timings: &mut HashMap<u64, SpinnerTiming>,
+) {
+    match o {
+        OutputType::Text(text) => format_output(ui, text),
+        OutputType::GroupHeader(_) | OutputType::SyncBegin | OutputType::SyncEnd => {}
+        OutputType::ProgressBar(ProgressBarState {
+            description,
+            value: ProgressValue::Fraction(value),
+            ..
+        }) => {
+            ui.add(
+                ProgressBar::new(*value)
+                    .text(description.as_str())
+                    .animate(true),
+            );
+        }
+        OutputType::ProgressBar(ProgressBarState {
+            description,
+            value:
+                ProgressValue::Count {
+                    position,
+                    length,
+                    bytes,
+                },
+            template,
+            ..
+        }) => {
+            let fraction = if *length > 0 {
+                *position as f32 / *length as f32
+            } else {
+                0.0
+            };
+            let timing = progress_timings.get(&id);
+            let rate = timing.map_or(0.0, ProgressTiming::rate);
+            let eta = timing.and_then(|timing| timing.eta(*position, *length));
+            let text = render_progress_template(
+                template.as_deref().unwrap_or(DEFAULT_COUNT_TEMPLATE),
+                description,
+                *position,
+                *length,
+                *bytes,
+                rate,
+                eta,
+            );
+            ui.add(ProgressBar::new(fraction).text(text).animate(true));
+        }
+        OutputType::Spinner(SpinnerState {
+            message, finished, ..
+        }) => {
+            let frame = if *finished {
+                SPINNER_FRAMES[0]
+            } else {
+                // Keep repainting on our own tick so the spinner advances
+                // even if the program doesn't send a new message for a while.
+                ui.ctx().request_repaint();
+                spinner_timings
+                    .entry(id)
+                    .or_insert_with(SpinnerTiming::new)
+                    .frame()
+            };
+            ui.label(format!("{} {}", frame, message));
+        }
+        OutputType::Highlight { language, code } => render_highlight_block(ui, language, code),
+        OutputType::JsonLog(JsonLogEntry {
+            style,
+            message,
+            main,
+            extra,
+        }) => {
+            ui.horizontal_wrapped(|ui| {
+                ui.add(style_label(message, *style));
+                for (key, value) in main {
+                    ui.label(format!("{key}={value}"));
+                }
+                for (key, value) in extra {
+                    ui.add(style_label(
+                        &format!("{key}={value}"),
+                        AnsiStyle {
+                            faint: true,
+                            ..AnsiStyle::default()
+                        },
+                    ));
+                }
+            });
+        }
+    }
+}
+
+/// Renders every entry sharing `group_id` as one contiguous, stably-ordered
+/// block (in first-seen order), with an optional header line and an
+/// aggregate bar summarizing completion across the group's members —
+/// mirroring how `indicatif::MultiProgress` stacks related bars together.
+fn render_group(
+    ui: &mut Ui,
+    entries: &[(u64, OutputType)],
+    progress_timings: &mut HashMap<u64, ProgressTiming>,
+    spinner_timings: &mut HashMap<u64, SpinnerTiming>,
+    group_id: u64,
+) {
+    let header = entries.iter().find_map(|(id, o)| match o {
+        OutputType::GroupHeader(header) if *id == group_id => Some(header.as_str()),
+        _ => None,
+    });
+
+    ui.group(|ui| {
+        if let Some(header) = header {
+            ui.add(Label::new(header).strong());
+        }
+
+        let mut total_value = 0.0;
+        let mut total_weight = 0.0;
+
+        for (id, o) in entries {
+            if o.group() != Some(group_id) {
+                continue;
+            }
+
             match o {
-                OutputType::Text(ref text) => format_output(ui, text),
-                OutputType::ProgressBar(ref mess, value) => {
-                    ui.add(ProgressBar::new(*value).text(mess).animate(true));
+                OutputType::ProgressBar(ProgressBarState {
+                    value: ProgressValue::Fraction(value),
+                    ..
+                }) => {
+                    total_value += *value as f64;
+                    total_weight += 1.0;
+                }
+                OutputType::ProgressBar(ProgressBarState {
+                    value:
+                        ProgressValue::Count {
+                            position, length, ..
+                        },
+                    ..
+                }) => {
+                    total_value += *position as f64;
+                    total_weight += *length as f64;
                 }
+                _ => {}
             }
+
+            render_entry(ui, *id, o, progress_timings, spinner_timings);
+        }
+
+        if total_weight > 0.0 {
+            ui.separator();
+            ui.add(
+                ProgressBar::new((total_value / total_weight) as f32)
+                    .text("total")
+                    .animate(true),
+            );
         }
+    });
+}
+
+/// Fills in the placeholders of a progress bar template. See
+/// [`progress_bar_with_template`] for the supported placeholder list.
+fn render_progress_template(
+    template: &str,
+    description: &str,
+    position: u64,
+    length: u64,
+    bytes: bool,
+    rate: f64,
+    eta: Option<Duration>,
+) -> String {
+    let (pos, len, rate) = if bytes {
+        (
+            format_bytes(position),
+            format_bytes(length),
+            format_bytes(rate as u64),
+        )
+    } else {
+        (
+            position.to_string(),
+            length.to_string(),
+            format!("{:.1}", rate),
+        )
+    };
+    let eta = eta.map_or_else(|| "-".to_string(), format_eta);
+
+    // {msg} is substituted last: description is caller-supplied and may
+    // itself contain text that looks like another placeholder (e.g.
+    // "batch_{pos}_job"), which must not be touched by the replacements below.
+    template
+        .replace("{bar}", "")
+        .replace("{wide_bar}", "")
+        .replace("{pos}", &pos)
+        .replace("{len}", &len)
+        .replace("{eta}", &eta)
+        .replace("{bytes_per_sec}", &rate)
+        .replace("{rate}", &rate)
+        .replace("{msg}", description)
+}
+
+/// Renders a byte count with a binary (KiB/MiB/...) unit suffix, matching
+/// `indicatif`'s `{bytes}` formatting.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+/// Renders a duration as a short human-readable ETA, e.g. `1h 05m` or `42s`.
+fn format_eta(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
     }
 }
 
@@ -118,111 +1054,460 @@ fn send_message(data: &[&str]) {
 
 impl OutputType {
     const PROGRESS_BAR_STR: &'static str = "progress-bar";
+    const PROGRESS_COUNT_STR: &'static str = "progress-count";
+    const SPINNER_STR: &'static str = "spinner";
+    const GROUP_HEADER_STR: &'static str = "group-header";
+    const HIGHLIGHT_STR: &'static str = "highlight";
+    const SYNC_BEGIN_STR: &'static str = "sync-begin";
+    const SYNC_END_STR: &'static str = "sync-end";
 
     pub fn send(self, id: u64) {
         match self {
             OutputType::Text(s) => print!("{}", s),
-            OutputType::ProgressBar(desc, value) => send_message(&[
+            OutputType::ProgressBar(ProgressBarState {
+                description,
+                value: ProgressValue::Fraction(value),
+                group,
+                ..
+            }) => send_message(&[
                 &id.to_string(),
                 Self::PROGRESS_BAR_STR,
-                &desc,
+                &description,
                 &value.to_string(),
+                &group_to_string(group),
+            ]),
+            OutputType::ProgressBar(ProgressBarState {
+                description,
+                value:
+                    ProgressValue::Count {
+                        position,
+                        length,
+                        bytes,
+                    },
+                template,
+                group,
+            }) => send_message(&[
+                &id.to_string(),
+                Self::PROGRESS_COUNT_STR,
+                &description,
+                &position.to_string(),
+                &length.to_string(),
+                if bytes { "1" } else { "0" },
+                template.as_deref().unwrap_or(""),
+                &group_to_string(group),
             ]),
+            OutputType::Spinner(SpinnerState {
+                message,
+                finished,
+                group,
+            }) => send_message(&[
+                &id.to_string(),
+                Self::SPINNER_STR,
+                &message,
+                if finished { "1" } else { "0" },
+                &group_to_string(group),
+            ]),
+            OutputType::GroupHeader(header) => {
+                send_message(&[&id.to_string(), Self::GROUP_HEADER_STR, &header])
+            }
+            OutputType::Highlight { language, code } => {
+                send_message(&[&id.to_string(), Self::HIGHLIGHT_STR, &language, &code])
+            }
+            OutputType::JsonLog(_) => {
+                unreachable!(
+                    "JsonLog entries are synthesized by Output::parse, never sent over the wire"
+                )
+            }
+            OutputType::SyncBegin => send_message(&[&id.to_string(), Self::SYNC_BEGIN_STR]),
+            OutputType::SyncEnd => send_message(&[&id.to_string(), Self::SYNC_END_STR]),
         }
     }
 
     pub fn parse<'a>(iter: &mut impl Iterator<Item = &'a str>) -> Option<Self> {
         match iter.next() {
-            Some(Self::PROGRESS_BAR_STR) => Some(Self::ProgressBar(
+            Some(Self::PROGRESS_BAR_STR) => Some(Self::ProgressBar(ProgressBarState {
+                description: iter.next().unwrap_or_default().to_string(),
+                value: ProgressValue::Fraction(
+                    iter.next()
+                        .map(|s| s.parse().ok())
+                        .flatten()
+                        .unwrap_or_default(),
+                ),
+                template: None,
+                group: iter.next().and_then(group_from_str),
+            })),
+            Some(Self::PROGRESS_COUNT_STR) => {
+                let description = iter.next().unwrap_or_default().to_string();
+                let position = iter.next().and_then(|s| s.parse().ok()).unwrap_or_default();
+                let length = iter.next().and_then(|s| s.parse().ok()).unwrap_or_default();
+                let bytes = iter.next() == Some("1");
+                let template = iter.next().filter(|s| !s.is_empty()).map(str::to_string);
+                let group = iter.next().and_then(group_from_str);
+                Some(Self::ProgressBar(ProgressBarState {
+                    description,
+                    value: ProgressValue::Count {
+                        position,
+                        length,
+                        bytes,
+                    },
+                    template,
+                    group,
+                }))
+            }
+            Some(Self::SPINNER_STR) => Some(Self::Spinner(SpinnerState {
+                message: iter.next().unwrap_or_default().to_string(),
+                finished: iter.next() == Some("1"),
+                group: iter.next().and_then(group_from_str),
+            })),
+            Some(Self::GROUP_HEADER_STR) => Some(Self::GroupHeader(
                 iter.next().unwrap_or_default().to_string(),
-                iter.next()
-                    .map(|s| s.parse().ok())
-                    .flatten()
-                    .unwrap_or_default(),
             )),
+            Some(Self::SYNC_BEGIN_STR) => Some(Self::SyncBegin),
+            Some(Self::SYNC_END_STR) => Some(Self::SyncEnd),
+            Some(Self::HIGHLIGHT_STR) => Some(Self::Highlight {
+                language: iter.next().unwrap_or_default().to_string(),
+                code: iter.next().unwrap_or_default().to_string(),
+            }),
             None => None,
             _ => panic!(),
         }
     }
 }
 
+fn group_to_string(group: Option<u64>) -> String {
+    group.map(|id| id.to_string()).unwrap_or_default()
+}
+
+fn group_from_str(s: &str) -> Option<u64> {
+    s.parse().ok()
+}
+
+/// A run of text sharing a single SGR style, produced by [`parse_ansi`].
+/// `link` is set while the span falls inside an OSC 8 hyperlink, carrying
+/// the target URL the text should point to.
+#[derive(Debug, Clone)]
+struct AnsiSpan {
+    text: String,
+    style: AnsiStyle,
+    link: Option<String>,
+}
+
+/// The subset of SGR (Select Graphic Rendition) state that affects rendering.
+/// `fg`/`bg` of `None` mean "use the default", matching how a real terminal
+/// treats SGR 39/49.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct AnsiStyle {
+    fg: Option<Color32>,
+    bg: Option<Color32>,
+    bold: bool,
+    faint: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+}
+
+/// The 16 named ANSI colors, indexed 0-15 (the same values `ansi_color_to_egui`
+/// used to return), also reused as the base of the xterm 256-color cube.
+const NAMED_COLORS: [Color32; 16] = [
+    Color32::from_rgb(0, 0, 0),
+    Color32::from_rgb(205, 49, 49),
+    Color32::from_rgb(13, 188, 121),
+    Color32::from_rgb(229, 229, 16),
+    Color32::from_rgb(36, 114, 200),
+    Color32::from_rgb(188, 63, 188),
+    Color32::from_rgb(17, 168, 205),
+    Color32::from_rgb(229, 229, 229),
+    Color32::from_rgb(102, 102, 102),
+    Color32::from_rgb(241, 76, 76),
+    Color32::from_rgb(35, 209, 139),
+    Color32::from_rgb(245, 245, 67),
+    Color32::from_rgb(59, 142, 234),
+    Color32::from_rgb(214, 112, 214),
+    Color32::from_rgb(41, 184, 219),
+    Color32::from_rgb(229, 229, 229),
+];
+
+/// `vte::Perform` implementation that turns a byte stream containing SGR
+/// (color/style) escape sequences into a flat list of [`AnsiSpan`]s. All
+/// other CSI sequences (cursor movement, erase, ...) are recognised and
+/// dropped rather than leaking into the rendered text.
+struct AnsiPerformer {
+    spans: Vec<AnsiSpan>,
+    style: AnsiStyle,
+    link: Option<String>,
+    buffer: String,
+}
+
+impl AnsiPerformer {
+    fn new() -> Self {
+        Self {
+            spans: Vec::new(),
+            style: AnsiStyle::default(),
+            link: None,
+            buffer: String::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            self.spans.push(AnsiSpan {
+                text: std::mem::take(&mut self.buffer),
+                style: self.style,
+                link: self.link.clone(),
+            });
+        }
+    }
+
+    fn apply_sgr(&mut self, params: &Params) {
+        let mut values = params.iter().flat_map(|p| p.iter().copied());
+        while let Some(code) = values.next() {
+            match code {
+                0 => self.style = AnsiStyle::default(),
+                1 => self.style.bold = true,
+                2 => self.style.faint = true,
+                3 => self.style.italic = true,
+                4 => self.style.underline = true,
+                9 => self.style.strikethrough = true,
+                22 => {
+                    self.style.bold = false;
+                    self.style.faint = false;
+                }
+                23 => self.style.italic = false,
+                24 => self.style.underline = false,
+                29 => self.style.strikethrough = false,
+                30..=37 => self.style.fg = Some(NAMED_COLORS[(code - 30) as usize]),
+                38 => self.style.fg = Self::extended_color(&mut values),
+                39 => self.style.fg = None,
+                40..=47 => self.style.bg = Some(NAMED_COLORS[(code - 40) as usize]),
+                48 => self.style.bg = Self::extended_color(&mut values),
+                49 => self.style.bg = None,
+                90..=97 => self.style.fg = Some(NAMED_COLORS[8 + (code - 90) as usize]),
+                100..=107 => self.style.bg = Some(NAMED_COLORS[8 + (code - 100) as usize]),
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses the `5;<idx>` (256-color) or `2;<r>;<g>;<b>` (truecolor) tail
+    /// of an SGR `38`/`48` extended color sequence.
+    fn extended_color(values: &mut impl Iterator<Item = u16>) -> Option<Color32> {
+        match values.next() {
+            Some(5) => values.next().map(xterm_256_to_rgb),
+            Some(2) => {
+                let r = values.next()?;
+                let g = values.next()?;
+                let b = values.next()?;
+                Some(Color32::from_rgb(r as u8, g as u8, b as u8))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Perform for AnsiPerformer {
+    fn print(&mut self, c: char) {
+        self.buffer.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if byte == b'\n' || byte == b'\r' || byte == b'\t' {
+            self.buffer.push(byte as char);
+        }
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        _intermediates: &[u8],
+        _ignore: bool,
+        action: char,
+    ) {
+        match action {
+            'm' => {
+                self.flush();
+                self.apply_sgr(params);
+            }
+            // Cursor movement (CSI ... H/A/B/C/D) and erase (CSI ... J/K)
+            // sequences carry no visible text of their own; dropping them
+            // here keeps them from leaking into the rendered output.
+            'H' | 'A' | 'B' | 'C' | 'D' | 'J' | 'K' => {}
+            _ => {}
+        }
+    }
+
+    /// Handles OSC 8 explicit hyperlinks (`ESC ] 8 ; params ; URI ST`),
+    /// e.g. `\x1b]8;;https://example.com\x1b\\ link text \x1b]8;;\x1b\\`.
+    /// An empty URI closes the currently open link.
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if params.first() != Some(&&b"8"[..]) {
+            return;
+        }
+
+        self.flush();
+        self.link = match params.get(2) {
+            Some(uri) if !uri.is_empty() => Some(String::from_utf8_lossy(uri).into_owned()),
+            _ => None,
+        };
+    }
+}
+
+/// Parses `text` for SGR color/style escape sequences using a `vte`
+/// state machine, returning the runs of text between style changes.
+fn parse_ansi(text: &str) -> Vec<AnsiSpan> {
+    let mut performer = AnsiPerformer::new();
+    let mut parser = Parser::new();
+    for byte in text.bytes() {
+        parser.advance(&mut performer, byte);
+    }
+    performer.flush();
+    performer.spans
+}
+
+/// xterm 256-color palette lookup: 0-15 are the named colors, 16-231 are a
+/// 6x6x6 RGB cube, and 232-255 are a 24-step grayscale ramp.
+fn xterm_256_to_rgb(index: u16) -> Color32 {
+    const RAMP: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => NAMED_COLORS[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let r = RAMP[(i / 36) as usize];
+            let g = RAMP[(i / 6 % 6) as usize];
+            let b = RAMP[(i % 6) as usize];
+            Color32::from_rgb(r, g, b)
+        }
+        232..=255 => {
+            let level = (8 + (index - 232) * 10) as u8;
+            Color32::from_rgb(level, level, level)
+        }
+        // Out of the 0-255 palette range: a malformed `38;5;<idx>`/`48;5;<idx>`
+        // shouldn't be able to panic the GUI, so just fall back to the default
+        // foreground color (used as-is for a `48;5;<idx>` background too).
+        _ => NAMED_COLORS[7],
+    }
+}
+
 fn format_output(ui: &mut Ui, text: &str) {
-    let output = cansi::categorise_text(text);
+    let spans = parse_ansi(text);
 
     let previous = ui.style().spacing.item_spacing;
     ui.style_mut().spacing.item_spacing = vec2(0.0, 0.0);
 
     ui.horizontal_wrapped(|ui| {
-        for CategorisedSlice {
-            text,
-            fg_colour,
-            bg_colour,
-            intensity,
-            italic,
-            underline,
-            strikethrough,
-            ..
-        } in output
-        {
+        for AnsiSpan { text, style, link } in &spans {
+            if let Some(target) = link {
+                ui.hyperlink_to(text, target);
+                continue;
+            }
+
             for span in LinkFinder::new().spans(text) {
                 match span.kind() {
                     Some(LinkKind::Url) => ui.hyperlink(span.as_str()),
                     Some(LinkKind::Email) => {
                         ui.hyperlink_to(span.as_str(), format!("mailto:{}", span.as_str()))
                     }
-                    Some(_) | None => {
-                        let mut label = Label::new(span.as_str());
+                    Some(_) | None => ui.add(style_label(span.as_str(), *style)),
+                };
+            }
+        }
+    });
+    ui.style_mut().spacing.item_spacing = previous;
+}
 
-                        label = label.text_color(ansi_color_to_egui(fg_colour));
+/// Builds a [`Label`] styled according to `style`, the shared rendering step
+/// behind both [`format_output`]'s ANSI spans and [`render_highlight_block`]'s
+/// syntect spans.
+fn style_label(text: &str, style: AnsiStyle) -> Label {
+    let mut label = Label::new(text);
 
-                        if bg_colour != Color::Black {
-                            label = label.background_color(ansi_color_to_egui(bg_colour));
-                        }
+    label = label.text_color(style.fg.unwrap_or(NAMED_COLORS[7]));
 
-                        if italic {
-                            label = label.italics();
-                        }
+    if let Some(bg) = style.bg {
+        label = label.background_color(bg);
+    }
 
-                        if underline {
-                            label = label.underline();
-                        }
+    if style.italic {
+        label = label.italics();
+    }
 
-                        if strikethrough {
-                            label = label.strikethrough();
-                        }
+    if style.underline {
+        label = label.underline();
+    }
 
-                        label = match intensity {
-                            cansi::Intensity::Normal => label,
-                            cansi::Intensity::Bold => label.strong(),
-                            cansi::Intensity::Faint => label.weak(),
-                        };
+    if style.strikethrough {
+        label = label.strikethrough();
+    }
 
-                        ui.add(label)
-                    }
-                };
-            }
+    if style.bold {
+        label.strong()
+    } else if style.faint {
+        label.weak()
+    } else {
+        label
+    }
+}
+
+/// Bundled syntax definitions used by [`render_highlight_block`], loaded once
+/// and reused across frames.
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Bundled dark theme used by [`render_highlight_block`], chosen to sit close
+/// to the muted palette [`NAMED_COLORS`] already renders ANSI output in.
+fn highlight_theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults().themes;
+        themes
+            .remove("base16-ocean.dark")
+            .expect("syntect bundles base16-ocean.dark")
+    })
+}
+
+/// Highlights `code` as `language` with `syntect` and renders it line by
+/// line, reusing [`style_label`] so highlighted code looks consistent with
+/// ANSI-colored output.
+fn render_highlight_block(ui: &mut Ui, language: &str, code: &str) {
+    let syntax = syntax_set()
+        .find_syntax_by_token(language)
+        .unwrap_or_else(|| syntax_set().find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, highlight_theme());
+
+    let previous = ui.style().spacing.item_spacing;
+    ui.style_mut().spacing.item_spacing = vec2(0.0, 0.0);
+
+    ui.vertical(|ui| {
+        for line in LinesWithEndings::from(code) {
+            let Ok(ranges) = highlighter.highlight_line(line, syntax_set()) else {
+                continue;
+            };
+
+            ui.horizontal_wrapped(|ui| {
+                for (style, text) in ranges {
+                    ui.add(style_label(text, syntect_style_to_ansi(style)));
+                }
+            });
         }
     });
+
     ui.style_mut().spacing.item_spacing = previous;
 }
 
-fn ansi_color_to_egui(color: Color) -> Color32 {
-    match color {
-        Color::Black => Color32::from_rgb(0, 0, 0),
-        Color::Red => Color32::from_rgb(205, 49, 49),
-        Color::Green => Color32::from_rgb(13, 188, 121),
-        Color::Yellow => Color32::from_rgb(229, 229, 16),
-        Color::Blue => Color32::from_rgb(36, 114, 200),
-        Color::Magenta => Color32::from_rgb(188, 63, 188),
-        Color::Cyan => Color32::from_rgb(17, 168, 205),
-        Color::White => Color32::from_rgb(229, 229, 229),
-        Color::BrightBlack => Color32::from_rgb(102, 102, 102),
-        Color::BrightRed => Color32::from_rgb(241, 76, 76),
-        Color::BrightGreen => Color32::from_rgb(35, 209, 139),
-        Color::BrightYellow => Color32::from_rgb(245, 245, 67),
-        Color::BrightBlue => Color32::from_rgb(59, 142, 234),
-        Color::BrightMagenta => Color32::from_rgb(214, 112, 214),
-        Color::BrightCyan => Color32::from_rgb(41, 184, 219),
-        Color::BrightWhite => Color32::from_rgb(229, 229, 229),
+/// Converts a `syntect` highlighting style into the [`AnsiStyle`] subset
+/// [`style_label`] knows how to render.
+fn syntect_style_to_ansi(style: syntect::highlighting::Style) -> AnsiStyle {
+    AnsiStyle {
+        fg: Some(Color32::from_rgb(
+            style.foreground.r,
+            style.foreground.g,
+            style.foreground.b,
+        )),
+        bold: style.font_style.contains(FontStyle::BOLD),
+        italic: style.font_style.contains(FontStyle::ITALIC),
+        underline: style.font_style.contains(FontStyle::UNDERLINE),
+        ..AnsiStyle::default()
     }
 }